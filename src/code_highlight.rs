@@ -0,0 +1,127 @@
+//! Syntax highlighting for fenced code blocks, backed by [syntect].
+//!
+//! Building a [SyntaxSet] and [ThemeSet] is expensive, so [CodeHighlighter] loads them once and
+//! is stored as a Bevy resource rather than being rebuilt for every code block.
+use bevy::{
+    asset::Handle,
+    color::Color,
+    ecs::system::Resource,
+    text::{Font, TextSection, TextStyle},
+};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color as SyntectColor, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+/// The syntect theme used when a style file does not request one of its own.
+pub(crate) const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Holds the syntect syntax and theme definitions used to highlight fenced code blocks.
+#[derive(Resource)]
+pub struct CodeHighlighter {
+    syntax_set: SyntaxSet,
+    theme_set: ThemeSet,
+}
+
+impl Default for CodeHighlighter {
+    fn default() -> Self {
+        Self {
+            syntax_set: SyntaxSet::load_defaults_newlines(),
+            theme_set: ThemeSet::load_defaults(),
+        }
+    }
+}
+
+impl CodeHighlighter {
+    /// Splits `code` into one [TextSection] per syntect highlight span, picking the syntax from
+    /// `lang` (falling back to plain text) and the theme named `theme` (falling back to
+    /// [DEFAULT_THEME]).
+    pub(crate) fn highlight(
+        &self,
+        code: &str,
+        lang: Option<&str>,
+        theme: &str,
+        font: Handle<Font>,
+        font_size: f32,
+    ) -> Vec<TextSection> {
+        let syntax = lang
+            .and_then(|lang| self.syntax_set.find_syntax_by_token(lang))
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+
+        let theme = self
+            .theme_set
+            .themes
+            .get(theme)
+            .unwrap_or(&self.theme_set.themes[DEFAULT_THEME]);
+
+        let mut highlighter = HighlightLines::new(syntax, theme);
+
+        LinesWithEndings::from(code)
+            .flat_map(|line| {
+                highlighter
+                    .highlight_line(line, &self.syntax_set)
+                    .unwrap_or_default()
+            })
+            .map(|(style, text)| TextSection {
+                value: text.to_string(),
+                style: TextStyle {
+                    font: font.clone(),
+                    font_size,
+                    color: syntect_color_to_bevy(style.foreground),
+                },
+            })
+            .collect()
+    }
+}
+
+fn syntect_color_to_bevy(color: SyntectColor) -> Color {
+    Color::srgba_u8(color.r, color.g, color.b, color.a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::Handle;
+
+    /// An unknown language should fall back to plain text rather than panicking
+    #[test]
+    fn test_unknown_language_falls_back_to_plain_text() {
+        let highlighter = CodeHighlighter::default();
+
+        let sections = highlighter.highlight(
+            "hello world",
+            Some("not-a-real-language"),
+            DEFAULT_THEME,
+            Handle::default(),
+            12.0,
+        );
+
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].value, "hello world");
+    }
+
+    /// An unknown theme name should fall back to DEFAULT_THEME rather than panicking
+    #[test]
+    fn test_unknown_theme_falls_back_to_default_theme() {
+        let highlighter = CodeHighlighter::default();
+        let code = "let x = 1;";
+
+        let with_bogus_theme =
+            highlighter.highlight(code, Some("rust"), "not-a-real-theme", Handle::default(), 12.0);
+        let with_default_theme =
+            highlighter.highlight(code, Some("rust"), DEFAULT_THEME, Handle::default(), 12.0);
+
+        let bogus_colors: Vec<String> = with_bogus_theme
+            .iter()
+            .map(|section| format!("{:?}", section.style.color))
+            .collect();
+        let default_colors: Vec<String> = with_default_theme
+            .iter()
+            .map(|section| format!("{:?}", section.style.color))
+            .collect();
+
+        assert_eq!(bogus_colors, default_colors);
+    }
+}
@@ -31,6 +31,12 @@
 //! - [ ] Subscript
 //! - [ ] Superscript
 //!
+//! Tables and footnotes are not implemented at all yet: the parser runs with pulldown_cmark's
+//! `ENABLE_TABLES` and `ENABLE_FOOTNOTES` left off, so a table or footnote reference in the
+//! source is parsed as ordinary paragraph text rather than crashing or silently dropping
+//! content. Turning on those extensions is blocked on this crate growing a
+//! [markdown_asset::MarkdownElement] variant (and renderer support) for each.
+//!
 //! # How to use
 //! To use this crate you just add the [MarkdownPlugin] to your bevy app
 //! and then add a [MarkdownNodeBundle] which contains a [Markdown] asset to an entity.
@@ -67,11 +73,15 @@ use bevy::{
     ui::Node,
     utils::HashSet,
 };
+use code_highlight::CodeHighlighter;
 use markdown_asset::Markdown;
+use markdown_link::{markdown_link_interaction, MarkdownLink, MarkdownLinkClicked};
 use markdown_loader::MarkdownLoader;
 use markdown_style::{MarkdownStyle, MarkdownStyleLoader};
 
+pub mod code_highlight;
 pub mod markdown_asset;
+pub mod markdown_link;
 pub mod markdown_loader;
 pub mod markdown_style;
 
@@ -84,7 +94,9 @@ impl Plugin for MarkdownPlugin {
             .init_asset::<MarkdownStyle>()
             .init_asset_loader::<MarkdownLoader>()
             .init_asset_loader::<MarkdownStyleLoader>()
-            .add_systems(Update, (on_add, on_asset_event));
+            .init_resource::<CodeHighlighter>()
+            .add_event::<MarkdownLinkClicked>()
+            .add_systems(Update, (on_add, on_asset_event, markdown_link_interaction));
     }
 }
 
@@ -106,6 +118,7 @@ fn on_asset_event(
     mut commands: Commands,
     markdown_assets: Res<Assets<Markdown>>,
     markdown_styles: Res<Assets<MarkdownStyle>>,
+    highlighter: Res<CodeHighlighter>,
     mut load_events: EventReader<AssetEvent<Markdown>>,
     nodes: Query<(Entity, &Handle<Markdown>, &Node)>,
 ) {
@@ -141,7 +154,7 @@ fn on_asset_event(
 
         commands
             .entity(entity)
-            .with_children(|c| build_markdown(c, markdown, style));
+            .with_children(|c| build_markdown(c, markdown, style, &highlighter));
     }
 }
 
@@ -149,6 +162,7 @@ fn on_add(
     mut commands: Commands,
     markdown_assets: Res<Assets<Markdown>>,
     markdown_styles: Res<Assets<MarkdownStyle>>,
+    highlighter: Res<CodeHighlighter>,
     asset_server: Res<AssetServer>,
     query: Query<(Entity, &Handle<Markdown>), Added<MarkdownComponent>>,
 ) {
@@ -172,44 +186,306 @@ fn on_add(
             debug!("markdown built when markdown was added");
             commands
                 .entity(entity)
-                .with_children(|c| build_markdown(c, markdown, style));
+                .with_children(|c| build_markdown(c, markdown, style, &highlighter));
         }
     }
 }
 
-fn build_markdown(builder: &mut ChildBuilder, markdown: &Markdown, style: &MarkdownStyle) {
-    let body_style: TextStyle = style.into();
+/// The left padding added per nesting level of a [MarkdownElement::List] or
+/// [MarkdownElement::BlockQuote]
+const NESTED_BLOCK_INDENT: f32 = 16.0;
 
-    let text_sections = markdown
-        .content
-        .iter()
-        .map(|element| match element {
-            markdown_asset::MarkdownElement::Text(text) => TextSection {
-                value: text.text.clone(),
-                style: body_style.clone(),
+fn build_markdown(
+    builder: &mut ChildBuilder,
+    markdown: &Markdown,
+    style: &MarkdownStyle,
+    highlighter: &CodeHighlighter,
+) {
+    if style.show_toc {
+        spawn_toc(builder, &markdown.table_of_contents(), style);
+    }
+
+    let default_style: TextStyle = style.into();
+    build_elements(builder, &markdown.content, style, &default_style, highlighter);
+}
+
+/// Spawns a document's table of contents as an indented column, one row per heading, mirroring
+/// the nesting [markdown_asset::TocEntry::children] already carries.
+fn spawn_toc(builder: &mut ChildBuilder, entries: &[markdown_asset::TocEntry], style: &MarkdownStyle) {
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                ..Default::default()
             },
-            markdown_asset::MarkdownElement::Heading(_, _) => todo!(),
-            markdown_asset::MarkdownElement::HorizontalRule => todo!(),
+            ..Default::default()
+        })
+        .with_children(|toc| {
+            for entry in entries {
+                toc.spawn(TextBundle::from_section(entry.text.clone(), style.into()));
+
+                if !entry.children.is_empty() {
+                    toc.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::left(Val::Px(NESTED_BLOCK_INDENT)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with_children(|nested| spawn_toc(nested, &entry.children, style));
+                }
+            }
+        });
+}
+
+fn build_elements(
+    builder: &mut ChildBuilder,
+    elements: &[markdown_asset::MarkdownElement],
+    style: &MarkdownStyle,
+    default_style: &TextStyle,
+    highlighter: &CodeHighlighter,
+) {
+    let mut sections: Vec<TextSection> = Vec::new();
+
+    for element in elements {
+        match element {
+            markdown_asset::MarkdownElement::Text(text) if text.is_link() => {
+                flush_text_sections(builder, &mut sections);
+                spawn_link(builder, style, default_style, text);
+            }
+            markdown_asset::MarkdownElement::Text(text) => sections.push(TextSection {
+                value: text.text.clone(),
+                style: style.resolve(element, default_style),
+            }),
+            markdown_asset::MarkdownElement::Heading { text, .. } => {
+                sections.push(TextSection {
+                    value: text.text.clone(),
+                    style: style.resolve(element, default_style),
+                });
+                sections.push(TextSection {
+                    value: "\n".to_string(),
+                    style: default_style.clone(),
+                });
+            }
+            markdown_asset::MarkdownElement::HorizontalRule => {
+                flush_text_sections(builder, &mut sections);
+                spawn_horizontal_rule(builder, default_style);
+            }
             markdown_asset::MarkdownElement::Image {
                 alt_text: _,
                 image: _,
             } => todo!(),
-            markdown_asset::MarkdownElement::OrderedListItem(_) => todo!(),
-            markdown_asset::MarkdownElement::UnorderedListItem(_) => todo!(),
-            markdown_asset::MarkdownElement::CodeBlock(_) => todo!(),
-            markdown_asset::MarkdownElement::LineBreak => TextSection {
+            markdown_asset::MarkdownElement::List { ordered, items } => {
+                flush_text_sections(builder, &mut sections);
+                spawn_list(builder, *ordered, items, style, default_style, highlighter);
+            }
+            markdown_asset::MarkdownElement::BlockQuote(children) => {
+                flush_text_sections(builder, &mut sections);
+                spawn_nested_block(builder, children, style, highlighter);
+            }
+            markdown_asset::MarkdownElement::CodeBlock { text, lang } => {
+                flush_text_sections(builder, &mut sections);
+                spawn_code_block(builder, text, lang.as_deref(), style, highlighter);
+            }
+            markdown_asset::MarkdownElement::LineBreak => sections.push(TextSection {
                 value: "\n".to_string(),
-                style: body_style.clone(),
+                style: default_style.clone(),
+            }),
+        }
+    }
+
+    flush_text_sections(builder, &mut sections);
+}
+
+/// Spawns a horizontal rule as a thin full-width bar
+fn spawn_horizontal_rule(builder: &mut ChildBuilder, default_style: &TextStyle) {
+    builder.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Px(2.0),
+            margin: UiRect::vertical(Val::Px(NESTED_BLOCK_INDENT / 2.0)),
+            ..Default::default()
+        },
+        background_color: BackgroundColor(default_style.color),
+        ..Default::default()
+    });
+}
+
+/// Spawns a list as an indented column, one row per item: a marker (`"1. "`, `"- "` or a task
+/// checkbox) followed by the item's own content, which may itself contain nested lists.
+fn spawn_list(
+    builder: &mut ChildBuilder,
+    ordered: bool,
+    items: &[markdown_asset::ListItem],
+    style: &MarkdownStyle,
+    default_style: &TextStyle,
+    highlighter: &CodeHighlighter,
+) {
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::left(Val::Px(NESTED_BLOCK_INDENT)),
+                ..Default::default()
             },
+            ..Default::default()
         })
-        .collect();
+        .with_children(|list| {
+            for (index, item) in items.iter().enumerate() {
+                let marker = match item.checked {
+                    Some(true) => "[x] ".to_string(),
+                    Some(false) => "[ ] ".to_string(),
+                    None if ordered => format!("{}. ", index + 1),
+                    None => "- ".to_string(),
+                };
+
+                list.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    row.spawn(TextBundle::from_section(marker, default_style.clone()));
+                    build_elements(row, &item.content, style, default_style, highlighter);
+                });
+            }
+        });
+}
+
+/// Spawns `children` inside a new indented [NodeBundle], recursing [build_elements] into it with
+/// the `blockquote` scope as the new default style for any plain text inside
+fn spawn_nested_block(
+    builder: &mut ChildBuilder,
+    children: &[markdown_asset::MarkdownElement],
+    style: &MarkdownStyle,
+    highlighter: &CodeHighlighter,
+) {
+    let quote_style = TextStyle {
+        font: style.font.clone(),
+        font_size: style.blockquote.size,
+        color: style.blockquote.color,
+    };
+
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::left(Val::Px(NESTED_BLOCK_INDENT)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|nested| build_elements(nested, children, style, &quote_style, highlighter));
+}
+
+/// Spawns a fenced code block as its own node with the `code` scope's background behind it
+fn spawn_code_block(
+    builder: &mut ChildBuilder,
+    text: &str,
+    lang: Option<&str>,
+    style: &MarkdownStyle,
+    highlighter: &CodeHighlighter,
+) {
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                padding: UiRect::all(Val::Px(NESTED_BLOCK_INDENT / 2.0)),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(style.code.background),
+            ..Default::default()
+        })
+        .with_children(|code_block| {
+            code_block.spawn(TextBundle {
+                text: Text {
+                    sections: highlighter.highlight(
+                        text,
+                        lang,
+                        &style.code.theme,
+                        style.code.font.clone(),
+                        style.code.size,
+                    ),
+                    justify: bevy::text::JustifyText::Left,
+                    linebreak_behavior: BreakLineOn::WordBoundary,
+                },
+                ..Default::default()
+            });
+        });
+}
+
+/// Spawns the accumulated plain-text sections as a single [TextBundle], if any
+fn flush_text_sections(builder: &mut ChildBuilder, sections: &mut Vec<TextSection>) {
+    if sections.is_empty() {
+        return;
+    }
 
     builder.spawn(TextBundle {
         text: Text {
-            sections: text_sections,
+            sections: std::mem::take(sections),
             justify: bevy::text::JustifyText::Left,
             linebreak_behavior: BreakLineOn::WordBoundary,
         },
         ..Default::default()
     });
 }
+
+/// Spawns a link as its own entity wrapping its text (and, when [markdown_style::LinkScope::underline]
+/// is set, a thin underline bar beneath it); [MarkdownLink] and [Interaction] sit on the wrapper
+/// so the whole link area is clickable and hoverable, not just the glyphs.
+fn spawn_link(
+    builder: &mut ChildBuilder,
+    style: &MarkdownStyle,
+    default_style: &TextStyle,
+    text: &markdown_asset::MarkdownText,
+) {
+    let markdown_asset::MarkdownTextStyle::Link { target, title } = &text.style else {
+        unreachable!("spawn_link is only called for MarkdownTextStyle::Link text")
+    };
+
+    let mut link_style = default_style.clone();
+    link_style.color = style.link.color;
+
+    builder
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Interaction::default(),
+            MarkdownLink {
+                target: target.clone(),
+                title: title.clone(),
+            },
+        ))
+        .with_children(|link| {
+            link.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: text.text.clone(),
+                        style: link_style,
+                    }],
+                    justify: bevy::text::JustifyText::Left,
+                    linebreak_behavior: BreakLineOn::WordBoundary,
+                },
+                ..Default::default()
+            });
+
+            if style.link.underline {
+                link.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(1.0),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(style.link.color),
+                    ..Default::default()
+                });
+            }
+        });
+}
@@ -0,0 +1,79 @@
+//! Makes links rendered from markdown interactive.
+//!
+//! Each rendered link is its own entity carrying a [MarkdownLink] component alongside Bevy's
+//! [Interaction]. [markdown_link_interaction] turns presses and hovers on those entities into
+//! [MarkdownLinkClicked] events that games can read to implement navigation or tooltips.
+use bevy::prelude::*;
+
+/// Marks an entity as a rendered markdown link
+#[derive(Component, Debug, Clone)]
+pub struct MarkdownLink {
+    pub target: String,
+    pub title: Option<String>,
+}
+
+/// Whether a [MarkdownLink] points at a heading in the same document, another document in the
+/// same project, or somewhere external
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownLinkTarget {
+    /// An in-document jump to a heading anchor id, e.g. `#some-heading`; resolve it against
+    /// [crate::markdown_asset::Markdown::table_of_contents] (or the heading elements directly)
+    /// to find the matching id
+    Anchor(String),
+    /// A relative path to another markdown document, e.g. `./docs.md`
+    Internal(String),
+    /// An absolute URL that should be opened outside the game, e.g. `https://example.com`
+    External(String),
+}
+
+impl MarkdownLinkTarget {
+    fn classify(target: &str) -> Self {
+        if let Some(id) = target.strip_prefix('#') {
+            MarkdownLinkTarget::Anchor(id.to_string())
+        } else if target.contains("://") {
+            MarkdownLinkTarget::External(target.to_string())
+        } else {
+            MarkdownLinkTarget::Internal(target.to_string())
+        }
+    }
+}
+
+/// Fired when a rendered markdown link is pressed or hovered
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownLinkClicked {
+    /// The link at `entity` pointing at `target` was pressed
+    Pressed {
+        entity: Entity,
+        target: MarkdownLinkTarget,
+    },
+    /// The link at `entity` pointing at `target` is being hovered, surfacing its `title` for a
+    /// tooltip
+    Hovered {
+        entity: Entity,
+        target: MarkdownLinkTarget,
+        title: Option<String>,
+    },
+}
+
+/// Turns [Interaction] changes on [MarkdownLink] entities into [MarkdownLinkClicked] events
+pub(crate) fn markdown_link_interaction(
+    mut link_events: EventWriter<MarkdownLinkClicked>,
+    links: Query<(Entity, &MarkdownLink, &Interaction), Changed<Interaction>>,
+) {
+    for (entity, link, interaction) in &links {
+        let target = MarkdownLinkTarget::classify(&link.target);
+        match interaction {
+            Interaction::Pressed => {
+                link_events.send(MarkdownLinkClicked::Pressed { entity, target });
+            }
+            Interaction::Hovered => {
+                link_events.send(MarkdownLinkClicked::Hovered {
+                    entity,
+                    target,
+                    title: link.title.clone(),
+                });
+            }
+            Interaction::None => {}
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
-use crate::markdown_asset::{parse_markdown, Markdown, MarkdownParseError};
-use bevy::{asset::AssetLoader, log::debug, tasks::futures_lite::io::BufReader};
+use crate::markdown_asset::{parse_markdown, Markdown};
+use bevy::asset::{AssetLoader, AsyncReadExt};
+use bevy::log::debug;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -16,9 +17,6 @@ pub enum MarkdownLoaderError {
     /// An [IO](std::io) Error
     #[error("Could not load asset: {0}")]
     Io(#[from] std::io::Error),
-    /// An [MarkdownParseError]
-    #[error("Could not parse markdown {0}")]
-    Parse(#[from] MarkdownParseError),
 }
 
 /// Settings for [MarkdownLoader]
@@ -41,8 +39,9 @@ impl AssetLoader for MarkdownLoader {
         debug!("Markdown load started");
         let style = load_context.load(settings.style.clone());
 
-        let buf_reader = BufReader::new(reader);
-        let content = parse_markdown(buf_reader).await?;
+        let mut source = String::new();
+        reader.read_to_string(&mut source).await?;
+        let content = parse_markdown(&source);
 
         debug!("Markdown load finnished");
         Ok(Markdown { content, style })
@@ -12,6 +12,28 @@
 //! Where font is a path to a compatible font which will be loaded into the engine as a font asset.
 //! body_size is the size of the font for normal body text.
 //! body_colour is the size for body text.
+//!
+//! # Scopes
+//! Beyond the body defaults, a style file can give `h1`..`h6`, `code`, `link`, `blockquote`,
+//! `bold`, `italic` and `strikethrough` their own look, the same way a code editor theme scopes
+//! syntax highlighting. Any scope (or field within a scope) that is left out falls back to the
+//! body style.
+//!
+//! Setting `show_toc` to `true` additionally renders the document's heading hierarchy (see
+//! [crate::markdown_asset::Markdown::table_of_contents]) as its own node before the content.
+//! ```ron
+//! (
+//!     font: "fonts\\Ubuntu\\Ubuntu-Regular.ttf",
+//!     monospace_font: "fonts\\FiraCode\\FiraCode-Regular.ttf",
+//!     body_size: 12.0,
+//!     body_color: Srgba((red: 1.0,green: 1.0,blue: 1.0,alpha: 1.0)),
+//!     h1: (size: 24.0),
+//!     code: (background: Srgba((red: 0.1,green: 0.1,blue: 0.1,alpha: 1.0)), theme: "base16-eighties.dark"),
+//!     link: (color: Srgba((red: 0.3,green: 0.5,blue: 1.0,alpha: 1.0)), underline: true),
+//!     bold: (color: Srgba((red: 1.0,green: 1.0,blue: 1.0,alpha: 1.0))),
+//!     show_toc: true,
+//! )
+//! ```
 use std::path::PathBuf;
 
 use bevy::{
@@ -25,22 +47,172 @@ use ron::de;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::{
+    code_highlight,
+    markdown_asset::{MarkdownElement, MarkdownText, MarkdownTextStyle},
+};
+
 type TextSize = f32;
 
+/// A scope that only varies font size and color from the body style, used for headings, block
+/// quotes, and the inline emphasis styles (bold, italic, strikethrough).
+#[derive(Debug, Clone, Copy)]
+pub struct TextScope {
+    pub size: TextSize,
+    pub color: Color,
+}
+
+/// The style used for fenced code blocks
+#[derive(Debug, Clone)]
+pub struct CodeScope {
+    pub font: Handle<Font>,
+    pub size: TextSize,
+    pub foreground: Color,
+    pub background: Color,
+    /// The syntect theme name used to highlight code block contents
+    pub theme: String,
+}
+
+/// The style used for link text
+#[derive(Debug, Clone, Copy)]
+pub struct LinkScope {
+    pub color: Color,
+    /// Whether a link is rendered with an underline bar beneath its text
+    pub underline: bool,
+}
+
 /// An asset used to store the style for a markdown file
 #[derive(Asset, TypePath)]
 pub struct MarkdownStyle {
     pub font: Handle<Font>,
     pub body_size: TextSize,
     pub text_color: Color,
+    /// Scopes for `h1` through `h6`, indexed by `level - 1`
+    pub headings: [TextScope; 6],
+    pub code: CodeScope,
+    pub link: LinkScope,
+    pub blockquote: TextScope,
+    pub bold: TextScope,
+    pub italic: TextScope,
+    pub strikethrough: TextScope,
+    /// Whether a table of contents node should be rendered before the document's content
+    pub show_toc: bool,
+}
+
+impl MarkdownStyle {
+    /// Resolves the [TextStyle] a [MarkdownElement] should be rendered with, falling back to
+    /// `default` (typically the body style, or a more specific one inherited from an enclosing
+    /// block such as a block quote) for anything that doesn't have a more specific scope.
+    pub fn resolve(&self, element: &MarkdownElement, default: &TextStyle) -> TextStyle {
+        match element {
+            MarkdownElement::Heading { level, .. } => {
+                let scope = &self.headings[(*level as usize).clamp(1, 6) - 1];
+                TextStyle {
+                    font: self.font.clone(),
+                    font_size: scope.size,
+                    color: scope.color,
+                }
+            }
+            MarkdownElement::CodeBlock { .. } => TextStyle {
+                font: self.code.font.clone(),
+                font_size: self.code.size,
+                color: self.code.foreground,
+            },
+            MarkdownElement::Text(text) => self.resolve_text_style(text, default),
+            _ => default.clone(),
+        }
+    }
+
+    /// Resolves the [TextStyle] for an inline text run, picking a scope that distinguishes it
+    /// from `default` for each [MarkdownTextStyle] the parser can produce.
+    fn resolve_text_style(&self, text: &MarkdownText, default: &TextStyle) -> TextStyle {
+        match &text.style {
+            MarkdownTextStyle::Standard => default.clone(),
+            MarkdownTextStyle::Bold => TextStyle {
+                font: default.font.clone(),
+                font_size: self.bold.size,
+                color: self.bold.color,
+            },
+            MarkdownTextStyle::Italic => TextStyle {
+                font: default.font.clone(),
+                font_size: self.italic.size,
+                color: self.italic.color,
+            },
+            MarkdownTextStyle::Strikethrough => TextStyle {
+                font: default.font.clone(),
+                font_size: self.strikethrough.size,
+                color: self.strikethrough.color,
+            },
+            MarkdownTextStyle::Code => TextStyle {
+                font: self.code.font.clone(),
+                font_size: self.code.size,
+                color: self.code.foreground,
+            },
+            MarkdownTextStyle::Link { .. } => TextStyle {
+                font: default.font.clone(),
+                font_size: default.font_size,
+                color: self.link.color,
+            },
+        }
+    }
+}
+
+/// A scope whose fields fall back to the body style when left out of the RON file
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ScopeRon {
+    size: Option<TextSize>,
+    color: Option<Color>,
+}
+
+/// The `code` scope, whose fields fall back to the body style when left out of the RON file
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CodeScopeRon {
+    size: Option<TextSize>,
+    foreground: Option<Color>,
+    background: Option<Color>,
+    theme: Option<String>,
+}
+
+/// The `link` scope, whose fields fall back to the body style when left out of the RON file
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LinkScopeRon {
+    color: Option<Color>,
+    underline: Option<bool>,
 }
 
 /// The desirialized style data
 #[derive(Debug, Deserialize, Serialize)]
 struct StyleRon {
     font: PathBuf,
+    monospace_font: Option<PathBuf>,
     body_size: TextSize,
     body_color: Color,
+    #[serde(default)]
+    h1: ScopeRon,
+    #[serde(default)]
+    h2: ScopeRon,
+    #[serde(default)]
+    h3: ScopeRon,
+    #[serde(default)]
+    h4: ScopeRon,
+    #[serde(default)]
+    h5: ScopeRon,
+    #[serde(default)]
+    h6: ScopeRon,
+    #[serde(default)]
+    code: CodeScopeRon,
+    #[serde(default)]
+    link: LinkScopeRon,
+    #[serde(default)]
+    blockquote: ScopeRon,
+    #[serde(default)]
+    bold: ScopeRon,
+    #[serde(default)]
+    italic: ScopeRon,
+    #[serde(default)]
+    strikethrough: ScopeRon,
+    #[serde(default)]
+    show_toc: bool,
 }
 
 #[non_exhaustive]
@@ -74,13 +246,52 @@ impl AssetLoader for MarkdownStyleLoader {
         let ron_data = de::from_bytes::<StyleRon>(&bytes)?;
 
         let font = load_context.load(ron_data.font);
+        let monospace_font = ron_data
+            .monospace_font
+            .map(|path| load_context.load(path))
+            .unwrap_or_else(|| font.clone());
+
+        let body_size = ron_data.body_size;
+        let body_color = ron_data.body_color;
+
+        let resolve_scope = |scope: ScopeRon| TextScope {
+            size: scope.size.unwrap_or(body_size),
+            color: scope.color.unwrap_or(body_color),
+        };
 
         debug!("Markdown style loaded");
 
         Ok(MarkdownStyle {
             font,
-            body_size: ron_data.body_size,
-            text_color: ron_data.body_color,
+            body_size,
+            text_color: body_color,
+            headings: [
+                resolve_scope(ron_data.h1),
+                resolve_scope(ron_data.h2),
+                resolve_scope(ron_data.h3),
+                resolve_scope(ron_data.h4),
+                resolve_scope(ron_data.h5),
+                resolve_scope(ron_data.h6),
+            ],
+            code: CodeScope {
+                font: monospace_font,
+                size: ron_data.code.size.unwrap_or(body_size),
+                foreground: ron_data.code.foreground.unwrap_or(body_color),
+                background: ron_data.code.background.unwrap_or(body_color),
+                theme: ron_data
+                    .code
+                    .theme
+                    .unwrap_or_else(|| code_highlight::DEFAULT_THEME.to_string()),
+            },
+            link: LinkScope {
+                color: ron_data.link.color.unwrap_or(body_color),
+                underline: ron_data.link.underline.unwrap_or(false),
+            },
+            blockquote: resolve_scope(ron_data.blockquote),
+            bold: resolve_scope(ron_data.bold),
+            italic: resolve_scope(ron_data.italic),
+            strikethrough: resolve_scope(ron_data.strikethrough),
+            show_toc: ron_data.show_toc,
         })
     }
 
@@ -89,22 +300,12 @@ impl AssetLoader for MarkdownStyleLoader {
     }
 }
 
-impl From<TextStyle> for MarkdownStyle {
-    fn from(value: TextStyle) -> Self {
-        Self {
-            font: value.font,
-            body_size: value.font_size,
-            text_color: value.color,
-        }
-    }
-}
-
-impl Into<TextStyle> for &MarkdownStyle {
-    fn into(self) -> TextStyle {
+impl From<&MarkdownStyle> for TextStyle {
+    fn from(value: &MarkdownStyle) -> Self {
         TextStyle {
-            font: self.font.clone(),
-            font_size: self.body_size,
-            color: self.text_color,
+            font: value.font.clone(),
+            font_size: value.body_size,
+            color: value.text_color,
         }
     }
 }